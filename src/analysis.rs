@@ -0,0 +1,229 @@
+// Semantic analysis: walks the parsed atom tree and builds a SymbolTable per
+// Group scope. Each scope is populated before it is resolved, so forward
+// references within the same scope (and references into enclosing scopes)
+// both work; only a reference that resolves against no scope in the chain
+// is an error.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::atom::span::Span;
+use crate::atom::{is_trivia, Atom, Group, GroupType, Identifier, IntegerLiteral, NumericLiteral, Operator, StringLiteral};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Type {
+    Int,
+    Float,
+    Str,
+    Array(Box<Type>, usize),
+    Unknown
+}
+
+impl Type {
+    fn from_name(name: &str) -> Option<Type> {
+        match name {
+            "Int" => Some(Type::Int),
+            "Float" => Some(Type::Float),
+            "String" => Some(Type::Str),
+            _ => None
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ValueSymbol {
+    pub name: String,
+    pub value_type: Type
+}
+
+#[derive(Default, Debug)]
+pub struct SymbolTable {
+    symbols: HashMap<String, ValueSymbol>
+}
+
+impl SymbolTable {
+    fn declare(&mut self, symbol: ValueSymbol) {
+        self.symbols.insert(symbol.name.clone(), symbol);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ValueSymbol> {
+        self.symbols.get(name)
+    }
+}
+
+#[derive(Debug)]
+pub enum AnalysisError {
+    UnknownIdentifier { name: String, span: Span }
+}
+
+pub struct Analysis {
+    pub tables: Vec<Rc<RefCell<SymbolTable>>>,
+    pub errors: Vec<AnalysisError>
+}
+
+pub fn analyze(root: &Group) -> Analysis {
+    let mut tables = Vec::new();
+    let mut errors = Vec::new();
+    let mut scopes: Vec<Rc<RefCell<SymbolTable>>> = Vec::new();
+    analyze_group(root, &mut scopes, &mut tables, &mut errors);
+    Analysis { tables, errors }
+}
+
+fn analyze_group(
+    group: &Group,
+    scopes: &mut Vec<Rc<RefCell<SymbolTable>>>,
+    tables: &mut Vec<Rc<RefCell<SymbolTable>>>,
+    errors: &mut Vec<AnalysisError>
+) {
+    let table = Rc::new(RefCell::new(SymbolTable::default()));
+    let children = group.children();
+    let mut consumed = vec![false; children.len()];
+    // Declarations are matched against only the non-trivia children, so
+    // whitespace/comments sitting between `name`, `:`/`=` and the type or
+    // value don't break the adjacency check.
+    let significant: Vec<usize> = children.iter().enumerate()
+        .filter(|(_, atom)| !is_trivia(atom))
+        .map(|(i, _)| i)
+        .collect();
+
+    // Pass 1: populate this scope's declarations before resolving anything,
+    // so declaration order within a scope doesn't matter.
+    for (pos, &i) in significant.iter().enumerate() {
+        let ident = match children[i].as_any().downcast_ref::<Identifier>() {
+            Some(ident) => ident,
+            None => continue
+        };
+        let j = match significant.get(pos + 1) {
+            Some(&j) => j,
+            None => continue
+        };
+        let op = match children[j].as_any().downcast_ref::<Operator>() {
+            Some(op) => op,
+            None => continue
+        };
+        match op.value() {
+            ":" => {
+                if let Some(&k) = significant.get(pos + 2) {
+                    if let Some(value_type) = resolve_type_annotation(children[k].as_ref()) {
+                        table.borrow_mut().declare(ValueSymbol { name: ident.value().to_owned(), value_type });
+                        consumed[i] = true;
+                        consumed[j] = true;
+                        consumed[k] = true;
+                    }
+                }
+            },
+            "=" => {
+                let value_type = significant.get(pos + 2)
+                    .and_then(|&k| literal_type(children[k].as_ref()))
+                    .unwrap_or(Type::Unknown);
+                table.borrow_mut().declare(ValueSymbol { name: ident.value().to_owned(), value_type });
+                consumed[i] = true;
+                consumed[j] = true;
+            },
+            _ => {}
+        }
+    }
+
+    scopes.push(table.clone());
+
+    // Pass 2: resolve every remaining identifier reference against the
+    // enclosing scope chain, and recurse into nested groups.
+    for (i, child) in children.iter().enumerate() {
+        if consumed[i] {
+            continue;
+        }
+        if let Some(ident) = child.as_any().downcast_ref::<Identifier>() {
+            if resolve(scopes, ident.value()).is_none() {
+                errors.push(AnalysisError::UnknownIdentifier {
+                    name: ident.value().to_owned(),
+                    span: ident.span()
+                });
+            }
+        } else if let Some(nested) = child.as_any().downcast_ref::<Group>() {
+            analyze_group(nested, scopes, tables, errors);
+        }
+    }
+
+    scopes.pop();
+    tables.push(table);
+}
+
+fn resolve(scopes: &[Rc<RefCell<SymbolTable>>], name: &str) -> Option<ValueSymbol> {
+    scopes.iter().rev().find_map(|table| table.borrow().get(name).cloned())
+}
+
+fn literal_type(atom: &dyn Atom) -> Option<Type> {
+    if atom.as_any().downcast_ref::<IntegerLiteral>().is_some() {
+        return Some(Type::Int);
+    }
+    if atom.as_any().downcast_ref::<NumericLiteral>().is_some() {
+        return Some(Type::Float);
+    }
+    if atom.as_any().downcast_ref::<StringLiteral>().is_some() {
+        return Some(Type::Str);
+    }
+    None
+}
+
+// A type annotation is either a primitive type identifier (`Int`) or a
+// sized-array literal (`[Int 3]`), which the lexer already parses as a
+// Square group holding an element-type identifier and a size literal.
+fn resolve_type_annotation(atom: &dyn Atom) -> Option<Type> {
+    if let Some(ident) = atom.as_any().downcast_ref::<Identifier>() {
+        return Type::from_name(ident.value());
+    }
+    if let Some(group) = atom.as_any().downcast_ref::<Group>() {
+        if group.group_type() == GroupType::Square {
+            let children = group.children();
+            let significant: Vec<&Box<dyn Atom>> = children.iter().filter(|a| !is_trivia(a)).collect();
+            if let [elem, size] = significant.as_slice() {
+                let elem_type = elem.as_any().downcast_ref::<Identifier>()
+                    .and_then(|ident| Type::from_name(ident.value()))?;
+                let size = size.as_any().downcast_ref::<IntegerLiteral>()
+                    .map(|lit| lit.value() as usize)?;
+                return Some(Type::Array(Box::new(elem_type), size));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::atom::lex;
+
+    #[test]
+    fn forward_reference_within_a_scope_resolves() {
+        let root = lex("x = y; y = 1;");
+        let analysis = analyze(&root);
+        assert!(analysis.errors.is_empty(), "unexpected errors: {:?}", analysis.errors);
+    }
+
+    #[test]
+    fn nested_scope_resolves_against_the_enclosing_scope() {
+        let root = lex("z = 5; { w = z; }");
+        let analysis = analyze(&root);
+        assert!(analysis.errors.is_empty(), "unexpected errors: {:?}", analysis.errors);
+    }
+
+    #[test]
+    fn array_type_annotation_resolves() {
+        let root = lex("arr : [Int 3];");
+        let analysis = analyze(&root);
+        assert!(analysis.errors.is_empty(), "unexpected errors: {:?}", analysis.errors);
+        let symbol = analysis.tables.last().unwrap().borrow().get("arr").cloned().expect("arr not declared");
+        assert_eq!(symbol.value_type, Type::Array(Box::new(Type::Int), 3));
+    }
+
+    #[test]
+    fn unknown_identifier_is_reported() {
+        let root = lex("{ c; }");
+        let analysis = analyze(&root);
+        match analysis.errors.as_slice() {
+            [AnalysisError::UnknownIdentifier { name, .. }] => assert_eq!(name, "c"),
+            other => panic!("expected a single UnknownIdentifier error, got {:?}", other)
+        }
+    }
+}
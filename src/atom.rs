@@ -1,13 +1,34 @@
-use std::cell::RefCell;
+use std::any::Any;
+use std::cell::{Ref, RefCell};
 use std::rc::Rc;
 
+pub mod expr;
+pub mod span;
+
+use span::{Diagnostic, DiagnosticKind, Position, Span};
+
 pub trait Atom {
-    fn read_char(self: Box<Self>, c: char) -> Box<dyn Atom>;
+    fn read_char(self: Box<Self>, c: char, pos: Position) -> Box<dyn Atom>;
+    // Called once at EOF to flush whatever atom is still being built back up
+    // to the Root, recording an unterminated-string/unterminated-group
+    // diagnostic for anything that didn't close cleanly on its own.
+    fn finish(self: Box<Self>) -> Box<dyn Atom>;
     fn name(self) -> String;
     fn debug_str(&self, tabs: usize) -> String;
+    fn as_any(&self) -> &dyn Any;
+    // Owned counterpart of as_any, for callers (like the evaluator) that
+    // need to consume a child atom and recover its concrete type.
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
 }
 const OPERATOR_SYMBOL: &str = "#@!%^&*-_+><=/\\~`,.|?:";
 const FINAL_OPERATOR: &str = ";";
+
+// Shared by the expr-folding and analysis passes: whitespace/comments are
+// meaningless to either and get skipped wherever they'd otherwise break an
+// adjacency check between two significant atoms.
+pub(crate) fn is_trivia(atom: &Box<dyn Atom>) -> bool {
+    atom.as_any().downcast_ref::<Whitespace>().is_some() || atom.as_any().downcast_ref::<Comment>().is_some()
+}
 fn take_parent_and_add_child(parent: Box<Group>, child: Box<dyn Atom>) -> Box<Group>{
     let v = parent.as_ref().children.clone();
     (*v.clone()).borrow_mut().push(child);
@@ -15,6 +36,7 @@ fn take_parent_and_add_child(parent: Box<Group>, child: Box<dyn Atom>) -> Box<Gr
 }
 
 // Group atom //==============================================================
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum GroupType {
     Root,
     Curly,
@@ -25,61 +47,143 @@ pub enum GroupType {
 pub struct Group {
     parent: Option<Box<Group>>,
     children: Rc<RefCell<Vec<Box<dyn Atom>>>>,
-    group_type: GroupType
+    diagnostics: Rc<RefCell<Vec<Diagnostic>>>,
+    group_type: GroupType,
+    span: Span
 }
 
 impl Group {
-    pub fn new(group_type: GroupType, parent: Option<Box<Group>>) -> Group {
+    pub fn new_root(pos: Position) -> Group {
         Group {
-            parent,
+            parent: None,
             children: Rc::new(RefCell::new(Vec::new())),
-            group_type
+            diagnostics: Rc::new(RefCell::new(Vec::new())),
+            group_type: GroupType::Root,
+            span: Span::point(pos)
         }
     }
 
+    pub fn new(group_type: GroupType, parent: Box<Group>, pos: Position) -> Group {
+        let diagnostics = parent.diagnostics.clone();
+        Group {
+            parent: Some(parent),
+            children: Rc::new(RefCell::new(Vec::new())),
+            diagnostics,
+            group_type,
+            span: Span::point(pos)
+        }
+    }
+
+    fn own_closing_char(&self) -> Option<char> {
+        match self.group_type {
+            GroupType::Root => None,
+            GroupType::Curly => Some('}'),
+            GroupType::Square => Some(']'),
+            GroupType::Curved => Some(')')
+        }
+    }
+
+    fn close(mut self: Box<Self>, c: char, pos: Position) -> Box<dyn Atom> {
+        match self.own_closing_char() {
+            None => {
+                self.diagnostics.borrow_mut().push(Diagnostic {
+                    kind: DiagnosticKind::UnmatchedClosingDelimiter { found: c },
+                    span: Span::point(pos)
+                });
+                self
+            },
+            Some(expected) => {
+                if expected != c {
+                    self.diagnostics.borrow_mut().push(Diagnostic {
+                        kind: DiagnosticKind::MismatchedClosingDelimiter { expected, found: c },
+                        span: Span::point(pos)
+                    });
+                }
+                let parent = self.parent.take().unwrap();
+                take_parent_and_add_child(parent, self)
+            }
+        }
+    }
 
-    pub fn read_default(mut self: Box<Self>, c: char) -> Box<dyn Atom> {
+    pub fn read_default(self: Box<Self>, c: char, pos: Position) -> Box<dyn Atom> {
         match c {
-            '{' => return Box::new(Group::new(GroupType::Curly, Some(self))),
-            '(' => return Box::new(Group::new(GroupType::Curved, Some(self))),
-            '[' => return Box::new(Group::new(GroupType::Square, Some(self))),
-            '\"' => return Box::new(StringLiteral::new(self)),
+            '{' => return Box::new(Group::new(GroupType::Curly, self, pos)),
+            '(' => return Box::new(Group::new(GroupType::Curved, self, pos)),
+            '[' => return Box::new(Group::new(GroupType::Square, self, pos)),
+            '\"' => return Box::new(StringLiteral::new(self, pos)),
             _ => {
                 if c.is_ascii_alphabetic() {
-                    return Box::new(Identifier::new(self)).read_char(c);
+                    return Box::new(Identifier::new(self, pos)).read_char(c, pos);
                 }else if c.is_ascii_digit(){
-                    return Box::new(IntegerLiteral::new(self)).read_char(c);
+                    return Box::new(IntegerLiteral::new(self, pos)).read_char(c, pos);
+                }else if c.is_whitespace() {
+                    return Box::new(Whitespace::new(self, pos)).read_char(c, pos);
                 }else if OPERATOR_SYMBOL.contains(c) || FINAL_OPERATOR.contains(c){
-                    return Box::new(Operator::new(self)).read_char(c);
+                    return Box::new(Operator::new(self, pos)).read_char(c, pos);
                 }
+                self.diagnostics.borrow_mut().push(Diagnostic {
+                    kind: DiagnosticKind::UnrecognizedCharacter { found: c },
+                    span: Span::point(pos)
+                });
                 return self;
             }
         };
     }
+
+    // Consumes this group's own children (not the parent's) and folds the
+    // flat sibling list into a precedence-correct Expr per `;`-terminated
+    // statement. Only meaningful once the group is finished, since it takes
+    // sole ownership of the children list.
+    pub fn into_expressions(self) -> Result<Vec<expr::Expr>, expr::ExprError> {
+        let children = match Rc::try_unwrap(self.children) {
+            Ok(cell) => cell.into_inner(),
+            Err(_) => panic!("group children are uniquely owned once the group is finished")
+        };
+        expr::parse_expressions(children)
+    }
+
+    // Like `into_expressions`, but for a Square group's comma/space-separated
+    // list elements rather than `;`-terminated statements.
+    pub fn into_list_items(self) -> Result<Vec<expr::Expr>, expr::ExprError> {
+        let children = match Rc::try_unwrap(self.children) {
+            Ok(cell) => cell.into_inner(),
+            Err(_) => panic!("group children are uniquely owned once the group is finished")
+        };
+        expr::parse_list_expressions(children)
+    }
+
+    pub fn group_type(&self) -> GroupType {
+        self.group_type
+    }
+
+    pub fn children(&self) -> Ref<'_, Vec<Box<dyn Atom>>> {
+        self.children.borrow()
+    }
+
+    pub fn diagnostics(&self) -> Ref<'_, Vec<Diagnostic>> {
+        self.diagnostics.borrow()
+    }
 }
 impl Atom for Group {
-    fn read_char(mut self: Box<Self>, c: char) -> Box<dyn Atom> {
+    fn read_char(mut self: Box<Self>, c: char, pos: Position) -> Box<dyn Atom> {
+        self.span.extend(pos);
+        match c {
+            '}' | ')' | ']' => self.close(c, pos),
+            _ => self.read_default(c, pos)
+        }
+    }
+
+    fn finish(mut self: Box<Self>) -> Box<dyn Atom> {
         match self.group_type {
-            GroupType::Root => {
-                self.read_default(c)
-            },
-            GroupType::Curly => {
-                match c {
-                    '}' => take_parent_and_add_child(self.parent.take().unwrap(), self),
-                    _ => self.read_default(c)
-                }
-            },
-            GroupType::Square => {
-                match c {
-                    ']' => take_parent_and_add_child(self.parent.take().unwrap(), self),
-                    _ => self.read_default(c)
-                }
-            },
-            GroupType::Curved => {
-                match c {
-                    ')' => take_parent_and_add_child(self.parent.take().unwrap(), self),
-                    _ => self.read_default(c)
-                }
+            GroupType::Root => self,
+            _ => {
+                let span = self.span;
+                self.diagnostics.borrow_mut().push(Diagnostic {
+                    kind: DiagnosticKind::UnterminatedGroup,
+                    span
+                });
+                let parent = self.parent.take().unwrap();
+                take_parent_and_add_child(parent, self).finish()
             }
         }
     }
@@ -97,11 +201,18 @@ impl Atom for Group {
         let mut build_string = String::new();
 
         build_string.push_str(match self.group_type {
-            GroupType::Root => "Root{\n",
-            GroupType::Curly => " curly{\n",
-            GroupType::Square => " square[\n",
+            GroupType::Root => "Root{",
+            GroupType::Curly => " curly{",
+            GroupType::Square => " square[",
             GroupType::Curved => " curved("
         });
+        build_string.push_str(&*self.span.debug_str());
+        build_string.push_str(match self.group_type {
+            GroupType::Root => "\n",
+            GroupType::Curly => "\n",
+            GroupType::Square => "\n",
+            GroupType::Curved => ""
+        });
         build_string.push_str(&*match self.group_type {
             GroupType::Root => "\t".repeat(tabs),
             GroupType::Curly => "\t".repeat(tabs),
@@ -130,33 +241,69 @@ impl Atom for Group {
             GroupType::Square => "]",
             GroupType::Curved => ")"
         });
+
+        if let GroupType::Root = self.group_type {
+            let diagnostics = self.diagnostics.borrow();
+            if !diagnostics.is_empty() {
+                build_string.push_str("\nDiagnostics:\n");
+                for diagnostic in diagnostics.iter() {
+                    build_string.push('\t');
+                    build_string.push_str(&*diagnostic.debug_str());
+                    build_string.push('\n');
+                }
+            }
+        }
         build_string
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
 }
 
 // Identifier atom //=========================================================
 pub struct Identifier {
     parent: Option<Box<Group>>,
-    value: String
+    value: String,
+    span: Span
 }
 impl Identifier {
-    pub fn new(parent: Box<Group>) -> Identifier {
+    pub fn new(parent: Box<Group>, pos: Position) -> Identifier {
         Identifier {
             parent: Some(parent),
-            value: String::new()
+            value: String::new(),
+            span: Span::point(pos)
         }
     }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
 }
 impl Atom for Identifier {
-    fn read_char(mut self: Box<Self>, c: char) -> Box<dyn Atom> {
+    fn read_char(mut self: Box<Self>, c: char, pos: Position) -> Box<dyn Atom> {
         if c.is_ascii_alphanumeric() {
             self.value.push(c);
+            self.span.extend(pos);
             return self;
         }else{
-            return take_parent_and_add_child(self.parent.take().unwrap(), self).read_char(c);
+            return take_parent_and_add_child(self.parent.take().unwrap(), self).read_char(c, pos);
         }
     }
 
+    fn finish(mut self: Box<Self>) -> Box<dyn Atom> {
+        let parent = self.parent.take().unwrap();
+        take_parent_and_add_child(parent, self).finish()
+    }
+
     fn name(self) -> String {
         "Identifier".to_owned()
     }
@@ -165,26 +312,46 @@ impl Atom for Identifier {
         let mut build_string = String::new();
         build_string.push_str(" iden:");
         build_string.push_str(&*self.value);
+        build_string.push('@');
+        build_string.push_str(&*self.span.debug_str());
         build_string
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
 }
 // String literal atom //=====================================================
 pub struct StringLiteral {
     parent: Option<Box<Group>>,
+    diagnostics: Rc<RefCell<Vec<Diagnostic>>>,
     value: String,
-    escaped: bool
+    escaped: bool,
+    span: Span
 }
 impl StringLiteral {
-    pub fn new(parent: Box<Group>) -> StringLiteral {
+    pub fn new(parent: Box<Group>, pos: Position) -> StringLiteral {
+        let diagnostics = parent.diagnostics.clone();
         StringLiteral {
             parent: Some(parent),
+            diagnostics,
             value: String::new(),
-            escaped: false
+            escaped: false,
+            span: Span::point(pos)
         }
     }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
 }
 impl Atom for StringLiteral {
-    fn read_char(mut self: Box<Self>, c: char) -> Box<dyn Atom> {
+    fn read_char(mut self: Box<Self>, c: char, pos: Position) -> Box<dyn Atom> {
+        self.span.extend(pos);
         if self.escaped {
             self.value.push(
                 match c {
@@ -215,6 +382,16 @@ impl Atom for StringLiteral {
         }
     }
 
+    fn finish(mut self: Box<Self>) -> Box<dyn Atom> {
+        let span = self.span;
+        self.diagnostics.borrow_mut().push(Diagnostic {
+            kind: DiagnosticKind::UnterminatedString,
+            span
+        });
+        let parent = self.parent.take().unwrap();
+        take_parent_and_add_child(parent, self).finish()
+    }
+
     fn name(self) -> String {
         "String Literal".to_owned()
     }
@@ -224,32 +401,160 @@ impl Atom for StringLiteral {
         build_string.push_str(" strLit(");
         build_string.push_str(&*self.value);
         build_string.push_str(")");
+        build_string.push('@');
+        build_string.push_str(&*self.span.debug_str());
         build_string
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+// Integer literal atom //=====================================================
+// Bases other than decimal are detected from a "0x"/"0o"/"0b" prefix on the
+// first two characters; everything else (including a later '.' or 'e'/'E')
+// only triggers decimal/float handling, matching how most languages treat
+// hex/octal/binary literals as integer-only.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum NumBase {
+    Dec,
+    Hex,
+    Oct,
+    Bin
+}
+
+impl NumBase {
+    fn radix(self) -> u32 {
+        match self {
+            NumBase::Dec => 10,
+            NumBase::Hex => 16,
+            NumBase::Oct => 8,
+            NumBase::Bin => 2
+        }
+    }
+
+    fn prefix_len(self) -> usize {
+        match self {
+            NumBase::Dec => 0,
+            NumBase::Hex | NumBase::Oct | NumBase::Bin => 2
+        }
+    }
+}
+
+// Shared by IntegerLiteral and NumericLiteral: '_' is accepted anywhere
+// inside a literal's digits as a separator, but stray/doubled/trailing
+// underscores are reported once the literal is finalized rather than
+// silently dropped.
+fn has_stray_separator(digits: &str) -> bool {
+    digits.starts_with('_') || digits.ends_with('_') || digits.contains("__")
 }
-// Integet literal atom //=====================================================
+
+fn strip_separators(text: &str) -> String {
+    text.chars().filter(|&c| c != '_').collect()
+}
+
+fn parse_int_text(text: &str, base: NumBase) -> Result<i64, String> {
+    let digits = &text[base.prefix_len()..];
+    if digits.is_empty() {
+        return Err(format!("'{}' has no digits after the base prefix", text));
+    }
+    if has_stray_separator(digits) {
+        return Err(format!("'{}' has a stray '_' digit separator", text));
+    }
+    i64::from_str_radix(&strip_separators(digits), base.radix())
+        .map_err(|err| err.to_string())
+}
+
 pub struct IntegerLiteral {
     parent: Option<Box<Group>>,
-    value: i64
+    diagnostics: Rc<RefCell<Vec<Diagnostic>>>,
+    text: String,
+    base: NumBase,
+    value: i64,
+    span: Span
 }
 impl IntegerLiteral {
-    pub fn new(parent: Box<Group>) -> IntegerLiteral {
+    pub fn new(parent: Box<Group>, pos: Position) -> IntegerLiteral {
+        let diagnostics = parent.diagnostics.clone();
         IntegerLiteral {
             parent: Some(parent),
-            value: 0
+            diagnostics,
+            text: String::new(),
+            base: NumBase::Dec,
+            value: 0,
+            span: Span::point(pos)
+        }
+    }
+
+    pub fn value(&self) -> i64 {
+        self.value
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    // Parses the accumulated text in one shot (rather than folding digits in
+    // incrementally), so a malformed literal can be reported with the full
+    // original text instead of whatever partial value had built up so far.
+    fn finalize_value(&mut self) {
+        match parse_int_text(&self.text, self.base) {
+            Ok(value) => self.value = value,
+            Err(reason) => {
+                self.value = 0;
+                let span = self.span;
+                self.diagnostics.borrow_mut().push(Diagnostic {
+                    kind: DiagnosticKind::MalformedNumber { text: self.text.clone(), reason },
+                    span
+                });
+            }
         }
     }
 }
 impl Atom for IntegerLiteral {
-    fn read_char(mut self: Box<Self>, c: char) -> Box<dyn Atom> {
-        if c.is_ascii_digit() {
-            self.value = self.value * 10 + c.to_digit(10).unwrap() as i64;
+    fn read_char(mut self: Box<Self>, c: char, pos: Position) -> Box<dyn Atom> {
+        if self.text.is_empty() {
+            self.text.push(c);
+            self.span.extend(pos);
+            return self;
+        }
+        if self.text == "0" && self.base == NumBase::Dec {
+            let prefixed = match c {
+                'x' => Some(NumBase::Hex),
+                'o' => Some(NumBase::Oct),
+                'b' => Some(NumBase::Bin),
+                _ => None
+            };
+            if let Some(base) = prefixed {
+                self.base = base;
+                self.text.push(c);
+                self.span.extend(pos);
+                return self;
+            }
+        }
+        if c == '_' || c.is_digit(self.base.radix()) {
+            self.text.push(c);
+            self.span.extend(pos);
             return self;
-        }else if c == '.'{
-            return Box::new(NumericLiteral::new(self.parent.take().unwrap(), self.value));
-        }else{
-            return take_parent_and_add_child(self.parent.take().unwrap(), self).read_char(c);
         }
+        if self.base == NumBase::Dec && (c == '.' || c == 'e' || c == 'E') {
+            self.span.extend(pos);
+            let mut text = self.text;
+            text.push(c);
+            return Box::new(NumericLiteral::from_digits(self.parent.take().unwrap(), text, self.span));
+        }
+        self.finalize_value();
+        take_parent_and_add_child(self.parent.take().unwrap(), self).read_char(c, pos)
+    }
+
+    fn finish(mut self: Box<Self>) -> Box<dyn Atom> {
+        self.finalize_value();
+        let parent = self.parent.take().unwrap();
+        take_parent_and_add_child(parent, self).finish()
     }
 
     fn name(self) -> String{
@@ -259,35 +564,133 @@ impl Atom for IntegerLiteral {
     fn debug_str(&self, tabs: usize) -> String {
         let mut build_string = String::new();
         build_string.push_str(" intLit(");
+        build_string.push_str(&*self.text);
+        build_string.push_str(" = ");
         build_string.push_str(&*self.value.to_string());
         build_string.push_str(")");
+        build_string.push('@');
+        build_string.push_str(&*self.span.debug_str());
         build_string
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
 }
 // Numeric literal atom //=====================================================
+// Always decimal: hex/octal/binary literals stay integers (see IntegerLiteral
+// above). Accumulates the literal's raw text and parses it with `f64`'s own
+// parser once finalized, rather than folding digits in one at a time, so the
+// value is exact and a malformed literal can still report its full text.
 pub struct NumericLiteral {
     parent: Option<Box<Group>>,
+    diagnostics: Rc<RefCell<Vec<Diagnostic>>>,
+    text: String,
     value: f64,
-    position: i32
+    seen_dot: bool,
+    seen_exponent: bool,
+    span: Span
 }
 impl NumericLiteral {
-    pub fn new(parent: Box<Group>, inital_value: i64) -> NumericLiteral {
-        NumericLiteral {
-            parent: Some(parent),
-            value: inital_value as f64,
-            position: 1
+    // `text` is whatever IntegerLiteral had already accumulated (digits and
+    // underscores, possibly followed by the '.' or 'e'/'E' that triggered
+    // the morph into a float).
+    fn from_digits(parent: Box<Group>, text: String, span: Span) -> NumericLiteral {
+        let diagnostics = parent.diagnostics.clone();
+        let seen_dot = text.ends_with('.');
+        let seen_exponent = text.ends_with('e') || text.ends_with('E');
+        NumericLiteral { parent: Some(parent), diagnostics, text, value: 0.0, seen_dot, seen_exponent, span }
+    }
+
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn finalize_value(&mut self) {
+        match parse_float_text(&self.text) {
+            Ok(value) => self.value = value,
+            Err(reason) => {
+                self.value = 0.0;
+                let span = self.span;
+                self.diagnostics.borrow_mut().push(Diagnostic {
+                    kind: DiagnosticKind::MalformedNumber { text: self.text.clone(), reason },
+                    span
+                });
+            }
         }
     }
+
+    // Cuts the literal short on a second '.' (or a '.' inside the exponent),
+    // reporting it and handing the offending character back to the parent so
+    // it still gets lexed on its own (typically as a standalone '.' operator).
+    fn reject_extra_dot(mut self: Box<Self>, pos: Position) -> Box<dyn Atom> {
+        let reason = "a numeric literal can't have two decimal points".to_owned();
+        self.diagnostics.borrow_mut().push(Diagnostic {
+            kind: DiagnosticKind::MalformedNumber { text: format!("{}.", self.text), reason },
+            span: Span::point(pos)
+        });
+        self.finalize_value();
+        let parent = self.parent.take().unwrap();
+        take_parent_and_add_child(parent, self)
+    }
+}
+
+fn parse_float_text(text: &str) -> Result<f64, String> {
+    if has_stray_separator(text) {
+        return Err(format!("'{}' has a stray '_' digit separator", text));
+    }
+    match text.chars().last() {
+        Some('.') | Some('e') | Some('E') | Some('+') | Some('-') => {
+            return Err(format!("'{}' is missing digits after the decimal point or exponent", text));
+        },
+        _ => {}
+    }
+    strip_separators(text).parse::<f64>().map_err(|err| err.to_string())
 }
+
 impl Atom for NumericLiteral {
-    fn read_char(mut self: Box<Self>, c: char) -> Box<dyn Atom> {
-        return if c.is_ascii_digit() {
-            self.value = self.value + (c.to_digit(10).unwrap() as f64) / 10.0f64.powi(self.position);
-            self.position += 1;
-            return self
-        } else {
-            take_parent_and_add_child(self.parent.take().unwrap(), self).read_char(c)
+    fn read_char(mut self: Box<Self>, c: char, pos: Position) -> Box<dyn Atom> {
+        if c.is_ascii_digit() || c == '_' {
+            self.text.push(c);
+            self.span.extend(pos);
+            return self;
         }
+        if c == '.' {
+            if self.seen_dot || self.seen_exponent {
+                return self.reject_extra_dot(pos).read_char(c, pos);
+            }
+            self.text.push(c);
+            self.seen_dot = true;
+            self.span.extend(pos);
+            return self;
+        }
+        if (c == 'e' || c == 'E') && !self.seen_exponent {
+            self.text.push(c);
+            self.seen_exponent = true;
+            self.span.extend(pos);
+            return self;
+        }
+        if (c == '+' || c == '-') && (self.text.ends_with('e') || self.text.ends_with('E')) {
+            self.text.push(c);
+            self.span.extend(pos);
+            return self;
+        }
+        self.finalize_value();
+        take_parent_and_add_child(self.parent.take().unwrap(), self).read_char(c, pos)
+    }
+
+    fn finish(mut self: Box<Self>) -> Box<dyn Atom> {
+        self.finalize_value();
+        let parent = self.parent.take().unwrap();
+        take_parent_and_add_child(parent, self).finish()
     }
 
     fn name(self) -> String {
@@ -297,37 +700,67 @@ impl Atom for NumericLiteral {
     fn debug_str(&self, tabs: usize) -> String {
         let mut build_string = String::new();
         build_string.push_str(" numLit(");
+        build_string.push_str(&*self.text);
+        build_string.push_str(" = ");
         build_string.push_str(&*self.value.to_string());
         build_string.push_str(")");
+        build_string.push('@');
+        build_string.push_str(&*self.span.debug_str());
         build_string
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
 }
 // Operator atom //===========================================================
 pub struct Operator {
     parent: Option<Box<Group>>,
-    value: String
+    value: String,
+    span: Span
 }
 impl Operator {
-    pub fn new(parent: Box<Group>) -> Operator {
+    pub fn new(parent: Box<Group>, pos: Position) -> Operator {
         Operator {
             parent: Some(parent),
-            value: String::new()
+            value: String::new(),
+            span: Span::point(pos)
         }
     }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
 }
 impl Atom for Operator {
-    fn read_char(mut self: Box<Self>, c: char) -> Box<dyn Atom> {
+    fn read_char(mut self: Box<Self>, c: char, pos: Position) -> Box<dyn Atom> {
+        if self.value == "/" && (c == '/' || c == '*') {
+            let kind = if c == '/' { CommentKind::Line } else { CommentKind::Block };
+            let parent = self.parent.take().unwrap();
+            return Box::new(Comment::new(parent, kind, self.value, self.span)).read_char(c, pos);
+        }
         if OPERATOR_SYMBOL.contains(c) {
             self.value.push(c);
+            self.span.extend(pos);
             return self;
         }else if FINAL_OPERATOR.contains(c){
             self.value.push(c);
+            self.span.extend(pos);
             return take_parent_and_add_child(self.parent.take().unwrap(), self);
         }else{
-            return take_parent_and_add_child(self.parent.take().unwrap(), self).read_char(c);
+            return take_parent_and_add_child(self.parent.take().unwrap(), self).read_char(c, pos);
         }
     }
 
+    fn finish(mut self: Box<Self>) -> Box<dyn Atom> {
+        let parent = self.parent.take().unwrap();
+        take_parent_and_add_child(parent, self).finish()
+    }
+
     fn name(self) -> String {
         "Operator".to_owned()
     }
@@ -337,6 +770,363 @@ impl Atom for Operator {
         build_string.push_str(" op(");
         build_string.push_str(&*self.value);
         build_string.push_str(")");
+        build_string.push('@');
+        build_string.push_str(&*self.span.debug_str());
+        build_string
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+// Comment atom //============================================================
+// Trivia: kept in the children list (rather than discarded) so a later
+// formatter can reconstruct the input verbatim. The analysis and eval passes
+// skip it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CommentKind {
+    Line,
+    Block
+}
+
+pub struct Comment {
+    parent: Option<Box<Group>>,
+    diagnostics: Rc<RefCell<Vec<Diagnostic>>>,
+    kind: CommentKind,
+    text: String,
+    span: Span
+}
+impl Comment {
+    // `text` is whatever of the opening delimiter the Operator atom had
+    // already accumulated (just "/") before it morphed into a comment.
+    pub fn new(parent: Box<Group>, kind: CommentKind, text: String, span: Span) -> Comment {
+        let diagnostics = parent.diagnostics.clone();
+        Comment { parent: Some(parent), diagnostics, kind, text, span }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn is_closed_block(&self) -> bool {
+        self.kind == CommentKind::Block && self.text.len() >= 4 && self.text.ends_with("*/")
+    }
+}
+impl Atom for Comment {
+    fn read_char(mut self: Box<Self>, c: char, pos: Position) -> Box<dyn Atom> {
+        match self.kind {
+            CommentKind::Line => {
+                if c == '\n' {
+                    return take_parent_and_add_child(self.parent.take().unwrap(), self).read_char(c, pos);
+                }
+                self.text.push(c);
+                self.span.extend(pos);
+                self
+            },
+            CommentKind::Block => {
+                self.text.push(c);
+                self.span.extend(pos);
+                if self.is_closed_block() {
+                    return take_parent_and_add_child(self.parent.take().unwrap(), self);
+                }
+                self
+            }
+        }
+    }
+
+    fn finish(mut self: Box<Self>) -> Box<dyn Atom> {
+        if self.kind == CommentKind::Block && !self.is_closed_block() {
+            let span = self.span;
+            self.diagnostics.borrow_mut().push(Diagnostic {
+                kind: DiagnosticKind::UnterminatedBlockComment,
+                span
+            });
+        }
+        let parent = self.parent.take().unwrap();
+        take_parent_and_add_child(parent, self).finish()
+    }
+
+    fn name(self) -> String {
+        "Comment".to_owned()
+    }
+
+    fn debug_str(&self, tabs: usize) -> String {
+        let mut build_string = String::new();
+        build_string.push_str(" comment(");
+        build_string.push_str(&*self.text);
+        build_string.push_str(")");
+        build_string.push('@');
+        build_string.push_str(&*self.span.debug_str());
         build_string
     }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+// Whitespace atom //=========================================================
+// Trivia: accumulates a run of consecutive whitespace (spaces, tabs,
+// newlines) as one atom, kept in the children list for the same round-trip
+// reason as Comment.
+pub struct Whitespace {
+    parent: Option<Box<Group>>,
+    text: String,
+    span: Span
+}
+impl Whitespace {
+    pub fn new(parent: Box<Group>, pos: Position) -> Whitespace {
+        Whitespace { parent: Some(parent), text: String::new(), span: Span::point(pos) }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+impl Atom for Whitespace {
+    fn read_char(mut self: Box<Self>, c: char, pos: Position) -> Box<dyn Atom> {
+        if c.is_whitespace() {
+            self.text.push(c);
+            self.span.extend(pos);
+            return self;
+        }
+        take_parent_and_add_child(self.parent.take().unwrap(), self).read_char(c, pos)
+    }
+
+    fn finish(mut self: Box<Self>) -> Box<dyn Atom> {
+        let parent = self.parent.take().unwrap();
+        take_parent_and_add_child(parent, self).finish()
+    }
+
+    fn name(self) -> String {
+        "Whitespace".to_owned()
+    }
+
+    fn debug_str(&self, tabs: usize) -> String {
+        let mut build_string = String::new();
+        build_string.push_str(" ws(");
+        build_string.push_str(&*self.text.escape_debug().to_string());
+        build_string.push_str(")");
+        build_string.push('@');
+        build_string.push_str(&*self.span.debug_str());
+        build_string
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+// Test-only helper shared by this module's own tests and by the expr/eval/
+// analysis tests: runs the same read_char/finish loop main() does, so every
+// layer can be exercised against real lexed source instead of hand-built atoms.
+#[cfg(test)]
+pub(crate) fn lex(src: &str) -> Group {
+    let mut pos = span::Position::start();
+    let mut state: Box<dyn Atom> = Box::new(Group::new_root(pos));
+    for c in src.chars() {
+        state = state.read_char(c, pos);
+        pos = pos.advance(c);
+    }
+    state = state.finish();
+    *state.into_any().downcast::<Group>().expect("finish() always bubbles back up to the Root group")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_int(src: &str) -> i64 {
+        let root = lex(src);
+        let children = root.children();
+        children.iter()
+            .find_map(|atom| atom.as_any().downcast_ref::<IntegerLiteral>())
+            .expect("no integer literal in the lexed source")
+            .value()
+    }
+
+    fn first_comment(src: &str) -> String {
+        let root = lex(src);
+        let children = root.children();
+        children.iter()
+            .find_map(|atom| atom.as_any().downcast_ref::<Comment>())
+            .expect("no comment in the lexed source")
+            .text()
+            .to_owned()
+    }
+
+    fn first_whitespace(src: &str) -> String {
+        let root = lex(src);
+        let children = root.children();
+        children.iter()
+            .find_map(|atom| atom.as_any().downcast_ref::<Whitespace>())
+            .expect("no whitespace in the lexed source")
+            .text()
+            .to_owned()
+    }
+
+    fn first_num(src: &str) -> f64 {
+        let root = lex(src);
+        let children = root.children();
+        children.iter()
+            .find_map(|atom| atom.as_any().downcast_ref::<NumericLiteral>())
+            .expect("no numeric literal in the lexed source")
+            .value()
+    }
+
+    #[test]
+    fn hex_octal_binary_bases_parse_as_ints() {
+        assert_eq!(parse_int_text("0xFF", NumBase::Hex), Ok(255));
+        assert_eq!(parse_int_text("0o17", NumBase::Oct), Ok(15));
+        assert_eq!(parse_int_text("0b101", NumBase::Bin), Ok(5));
+    }
+
+    #[test]
+    fn underscores_are_stripped_before_parsing() {
+        assert_eq!(parse_int_text("1_000_000", NumBase::Dec), Ok(1_000_000));
+        assert_eq!(parse_float_text("1_0.5"), Ok(10.5));
+    }
+
+    #[test]
+    fn stray_underscore_is_rejected() {
+        assert!(parse_int_text("1__0", NumBase::Dec).is_err());
+        assert!(parse_int_text("5_", NumBase::Dec).is_err());
+        assert!(parse_int_text("_5", NumBase::Dec).is_err());
+    }
+
+    #[test]
+    fn missing_digits_after_base_prefix_is_rejected() {
+        assert!(parse_int_text("0x", NumBase::Hex).is_err());
+    }
+
+    #[test]
+    fn exponent_and_dangling_separators_are_rejected_in_floats() {
+        assert_eq!(parse_float_text("1e9"), Ok(1e9));
+        assert_eq!(parse_float_text("2.5e-3"), Ok(2.5e-3));
+        assert!(parse_float_text("1.").is_err());
+        assert!(parse_float_text("1e").is_err());
+        assert!(parse_float_text("1.5_").is_err());
+    }
+
+    #[test]
+    fn integer_literal_accepts_underscores_and_hex() {
+        assert_eq!(first_int("0xFF;"), 255);
+        assert_eq!(first_int("1_000;"), 1000);
+    }
+
+    #[test]
+    fn second_decimal_point_is_malformed() {
+        // "1.2.3" should finalize at the first extra '.' rather than panic.
+        assert_eq!(first_num("1.2.3;"), 1.2);
+        let root = lex("1.2.3;");
+        let diagnostics = root.diagnostics();
+        match diagnostics.as_slice() {
+            [Diagnostic { kind: DiagnosticKind::MalformedNumber { .. }, .. }] => {},
+            other => panic!("expected a single MalformedNumber, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn unmatched_closing_delimiter_is_reported() {
+        let root = lex("x = 1 );");
+        let diagnostics = root.diagnostics();
+        match diagnostics.as_slice() {
+            [Diagnostic { kind: DiagnosticKind::UnmatchedClosingDelimiter { found: ')' }, .. }] => {},
+            other => panic!("expected a single UnmatchedClosingDelimiter, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn mismatched_closing_delimiter_is_reported() {
+        let root = lex("(1, 2];");
+        let children = root.children();
+        let group = children.iter()
+            .find_map(|atom| atom.as_any().downcast_ref::<Group>())
+            .expect("no nested group in the lexed source");
+        let diagnostics = group.diagnostics();
+        match diagnostics.as_slice() {
+            [Diagnostic { kind: DiagnosticKind::MismatchedClosingDelimiter { expected: ')', found: ']' }, .. }] => {},
+            other => panic!("expected a single MismatchedClosingDelimiter, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn unterminated_string_is_reported() {
+        let root = lex("x = \"abc");
+        let diagnostics = root.diagnostics();
+        match diagnostics.as_slice() {
+            [Diagnostic { kind: DiagnosticKind::UnterminatedString, .. }] => {},
+            other => panic!("expected a single UnterminatedString, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn unterminated_group_is_reported() {
+        let root = lex("{ x = 1;");
+        let diagnostics = root.diagnostics();
+        match diagnostics.as_slice() {
+            [Diagnostic { kind: DiagnosticKind::UnterminatedGroup, .. }] => {},
+            other => panic!("expected a single UnterminatedGroup, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn unrecognized_character_is_reported_instead_of_dropped() {
+        let root = lex("x = 1 $ 2;");
+        let diagnostics = root.diagnostics();
+        match diagnostics.as_slice() {
+            [Diagnostic { kind: DiagnosticKind::UnrecognizedCharacter { found: '$' }, .. }] => {},
+            other => panic!("expected a single UnrecognizedCharacter, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn line_comment_runs_to_the_newline() {
+        assert_eq!(first_comment("// a comment\nx;"), "// a comment");
+    }
+
+    #[test]
+    fn block_comment_captures_everything_between_the_delimiters() {
+        assert_eq!(first_comment("/* a block */ x;"), "/* a block */");
+    }
+
+    #[test]
+    fn unterminated_block_comment_is_reported() {
+        let root = lex("/* never closed");
+        let diagnostics = root.diagnostics();
+        match diagnostics.as_slice() {
+            [Diagnostic { kind: DiagnosticKind::UnterminatedBlockComment, .. }] => {},
+            other => panic!("expected a single UnterminatedBlockComment, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn whitespace_accumulates_into_one_atom() {
+        assert_eq!(first_whitespace("x   \t\n  = 1;"), "   \t\n  ");
+    }
+
+    #[test]
+    fn integer_and_numeric_literals_expose_their_original_text() {
+        let root = lex("0x1_0; 1_0.5;");
+        let children = root.children();
+        assert_eq!(children.iter()
+            .find_map(|atom| atom.as_any().downcast_ref::<IntegerLiteral>())
+            .expect("no integer literal in the lexed source")
+            .text(), "0x1_0");
+        assert_eq!(children.iter()
+            .find_map(|atom| atom.as_any().downcast_ref::<NumericLiteral>())
+            .expect("no numeric literal in the lexed source")
+            .text(), "1_0.5");
+    }
 }
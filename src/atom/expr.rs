@@ -0,0 +1,223 @@
+use std::collections::VecDeque;
+
+use super::{is_trivia, Atom, Operator, FINAL_OPERATOR};
+
+// Operators that may also appear in prefix position (e.g. `-a`, `!flag`).
+const PREFIX_OPERATORS: &str = "-!";
+// Separates list-literal elements; never a binary operator in its own right,
+// so it always terminates an expression rather than being folded into one.
+const LIST_SEPARATOR: &str = ",";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Assoc {
+    Left,
+    Right
+}
+
+// Binding power table for precedence climbing. Higher binds tighter.
+fn precedence(op: &str) -> Option<(u8, Assoc)> {
+    Some(match op {
+        "=" => (1, Assoc::Right),
+        "||" => (2, Assoc::Left),
+        "&&" => (3, Assoc::Left),
+        "==" | "!=" => (4, Assoc::Left),
+        "<" | ">" | "<=" | ">=" => (5, Assoc::Left),
+        "+" | "-" => (6, Assoc::Left),
+        "*" | "/" | "%" => (7, Assoc::Left),
+        "^" => (8, Assoc::Right),
+        _ => return None
+    })
+}
+
+// Expr AST //=================================================================
+// Produced by folding a finished Group's flat `children` list into a
+// precedence-correct tree. Leaves wrap whatever atom the lexer already built
+// (an identifier, literal, or nested group) so this pass never re-parses text.
+pub enum Expr {
+    Leaf(Box<dyn Atom>),
+    Unary { op: String, operand: Box<Expr> },
+    Binary { op: String, lhs: Box<Expr>, rhs: Box<Expr> }
+}
+
+#[derive(Debug)]
+pub enum ExprError {
+    EmptyGroup,
+    ExpectedOperand,
+    UnknownOperator(String),
+    TrailingOperator(String)
+}
+
+// Folds a finished group's children into one expression per `;`-terminated
+// statement. An empty group (no children at all) is itself an error, but a
+// a trailing empty statement (two `;` in a row, or a `;` right before the
+// closing bracket) is simply dropped, same as the source text would read.
+pub fn parse_expressions(children: Vec<Box<dyn Atom>>) -> Result<Vec<Expr>, ExprError> {
+    if children.is_empty() {
+        return Err(ExprError::EmptyGroup);
+    }
+    let mut tokens: VecDeque<Box<dyn Atom>> = children.into();
+    let mut exprs = Vec::new();
+    drop_trivia(&mut tokens);
+    while !tokens.is_empty() {
+        if is_final_operator(tokens.front()) {
+            tokens.pop_front();
+            drop_trivia(&mut tokens);
+            continue;
+        }
+        exprs.push(parse_expr(&mut tokens, 0)?);
+        drop_trivia(&mut tokens);
+        match tokens.pop_front() {
+            None => break,
+            Some(atom) => {
+                if !is_final_operator(Some(&atom)) {
+                    let op = atom.as_any().downcast_ref::<Operator>()
+                        .map(|op| op.value.clone())
+                        .unwrap_or_else(|| "<non-operator>".to_owned());
+                    return Err(ExprError::TrailingOperator(op));
+                }
+                drop_trivia(&mut tokens);
+            }
+        }
+    }
+    Ok(exprs)
+}
+
+// Folds a Square group's children into one expression per list element.
+// Unlike `parse_expressions`, elements aren't `;`-terminated statements: they
+// may be separated by a ',' or simply by whitespace (`[1, 2, 3]` and
+// `[1 2 3]` both hold three elements), and an empty group is a valid empty
+// list rather than an error.
+pub fn parse_list_expressions(children: Vec<Box<dyn Atom>>) -> Result<Vec<Expr>, ExprError> {
+    let mut tokens: VecDeque<Box<dyn Atom>> = children.into();
+    let mut exprs = Vec::new();
+    drop_trivia(&mut tokens);
+    while !tokens.is_empty() {
+        exprs.push(parse_expr(&mut tokens, 0)?);
+        drop_trivia(&mut tokens);
+        if is_list_separator(tokens.front()) {
+            tokens.pop_front();
+            drop_trivia(&mut tokens);
+        }
+    }
+    Ok(exprs)
+}
+
+fn is_list_separator(atom: Option<&Box<dyn Atom>>) -> bool {
+    atom.and_then(|a| a.as_any().downcast_ref::<Operator>())
+        .map_or(false, |op| op.value == LIST_SEPARATOR)
+}
+
+fn drop_trivia(tokens: &mut VecDeque<Box<dyn Atom>>) {
+    while tokens.front().map_or(false, is_trivia) {
+        tokens.pop_front();
+    }
+}
+
+fn is_final_operator(atom: Option<&Box<dyn Atom>>) -> bool {
+    atom.and_then(|a| a.as_any().downcast_ref::<Operator>())
+        .map_or(false, |op| op.value == FINAL_OPERATOR)
+}
+
+fn peek_operator(tokens: &mut VecDeque<Box<dyn Atom>>) -> Option<String> {
+    drop_trivia(tokens);
+    tokens.front()
+        .and_then(|a| a.as_any().downcast_ref::<Operator>())
+        .map(|op| op.value.clone())
+}
+
+// Classic precedence climbing: take a primary operand, then keep folding in
+// binary operators whose precedence is at least `min_prec`, recursing with
+// `prec + 1` (left-associative) or `prec` (right-associative) for the rhs.
+fn parse_expr(tokens: &mut VecDeque<Box<dyn Atom>>, min_prec: u8) -> Result<Expr, ExprError> {
+    let mut lhs = parse_primary(tokens)?;
+    loop {
+        let op = match peek_operator(tokens) {
+            Some(op) if op != FINAL_OPERATOR && op != LIST_SEPARATOR => op,
+            _ => break
+        };
+        let (prec, assoc) = precedence(&op).ok_or_else(|| ExprError::UnknownOperator(op.clone()))?;
+        if prec < min_prec {
+            break;
+        }
+        tokens.pop_front();
+        let next_min = match assoc {
+            Assoc::Left => prec + 1,
+            Assoc::Right => prec
+        };
+        let rhs = parse_expr(tokens, next_min)?;
+        lhs = Expr::Binary { op, lhs: Box::new(lhs), rhs: Box::new(rhs) };
+    }
+    Ok(lhs)
+}
+
+fn parse_primary(tokens: &mut VecDeque<Box<dyn Atom>>) -> Result<Expr, ExprError> {
+    drop_trivia(tokens);
+    let atom = tokens.pop_front().ok_or(ExprError::ExpectedOperand)?;
+    if let Some(op) = atom.as_any().downcast_ref::<Operator>() {
+        let op_value = op.value.clone();
+        if PREFIX_OPERATORS.contains(&op_value) && op_value.len() == 1 {
+            let operand = parse_primary(tokens)?;
+            return Ok(Expr::Unary { op: op_value, operand: Box::new(operand) });
+        }
+        return Err(ExprError::TrailingOperator(op_value));
+    }
+    Ok(Expr::Leaf(atom))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::atom::lex;
+
+    fn parse_one(src: &str) -> Expr {
+        let root = lex(src);
+        root.into_expressions().unwrap().into_iter().next().expect("no expression parsed")
+    }
+
+    #[test]
+    fn multiplication_binds_tighter_than_addition() {
+        // 1 + 2 * 3 should fold as 1 + (2 * 3), not (1 + 2) * 3.
+        match parse_one("1 + 2 * 3;") {
+            Expr::Binary { op, rhs, .. } => {
+                assert_eq!(op, "+");
+                assert!(matches!(*rhs, Expr::Binary { .. }));
+            },
+            other => panic!("expected a binary expr, got {:?}", std::mem::discriminant(&other))
+        }
+    }
+
+    #[test]
+    fn caret_is_right_associative() {
+        // 2 ^ 3 ^ 2 should fold as 2 ^ (3 ^ 2), not (2 ^ 3) ^ 2.
+        match parse_one("2 ^ 3 ^ 2;") {
+            Expr::Binary { op, lhs, rhs } => {
+                assert_eq!(op, "^");
+                assert!(matches!(*lhs, Expr::Leaf(_)));
+                assert!(matches!(*rhs, Expr::Binary { .. }));
+            },
+            other => panic!("expected a binary expr, got {:?}", std::mem::discriminant(&other))
+        }
+    }
+
+    #[test]
+    fn trailing_operator_is_an_error() {
+        // `+` isn't a valid prefix operator, so this is a binary operator
+        // where the rhs operand should be, not an actual trailing `;`.
+        let root = lex("1 + + 2;");
+        match root.into_expressions() {
+            Err(ExprError::TrailingOperator(op)) => assert_eq!(op, "+"),
+            Err(other) => panic!("expected TrailingOperator, got {:?}", other),
+            Ok(_) => panic!("expected TrailingOperator, parsing succeeded")
+        }
+    }
+
+    #[test]
+    fn empty_group_is_an_error() {
+        let root = lex("");
+        match root.into_expressions() {
+            Err(ExprError::EmptyGroup) => {},
+            Err(other) => panic!("expected EmptyGroup, got {:?}", other),
+            Ok(_) => panic!("expected EmptyGroup, parsing succeeded")
+        }
+    }
+}
@@ -0,0 +1,84 @@
+// Source positions and the diagnostics list that accumulates on the Root
+// group, so a bad lexer state produces a located error instead of a panic
+// or a silent drop.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Position {
+    pub byte: usize,
+    pub line: usize,
+    pub column: usize
+}
+
+impl Position {
+    pub fn start() -> Position {
+        Position { byte: 0, line: 1, column: 1 }
+    }
+
+    // Position of the char *after* `c`, given `c` sits at `self`.
+    pub fn advance(&self, c: char) -> Position {
+        if c == '\n' {
+            Position { byte: self.byte + c.len_utf8(), line: self.line + 1, column: 1 }
+        } else {
+            Position { byte: self.byte + c.len_utf8(), line: self.line, column: self.column + 1 }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position
+}
+
+impl Span {
+    pub fn point(pos: Position) -> Span {
+        Span { start: pos, end: pos }
+    }
+
+    pub fn extend(&mut self, pos: Position) {
+        self.end = pos;
+    }
+
+    pub fn debug_str(&self) -> String {
+        format!("{}:{}-{}:{}", self.start.line, self.start.column, self.end.line, self.end.column)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum DiagnosticKind {
+    UnmatchedClosingDelimiter { found: char },
+    MismatchedClosingDelimiter { expected: char, found: char },
+    UnterminatedString,
+    UnterminatedBlockComment,
+    UnterminatedGroup,
+    MalformedNumber { text: String, reason: String },
+    UnrecognizedCharacter { found: char }
+}
+
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub span: Span
+}
+
+impl Diagnostic {
+    pub fn debug_str(&self) -> String {
+        let message = match &self.kind {
+            DiagnosticKind::UnmatchedClosingDelimiter { found } =>
+                format!("unmatched closing delimiter '{}'", found),
+            DiagnosticKind::MismatchedClosingDelimiter { expected, found } =>
+                format!("expected closing delimiter '{}', found '{}'", expected, found),
+            DiagnosticKind::UnterminatedString =>
+                "unterminated string literal".to_owned(),
+            DiagnosticKind::UnterminatedBlockComment =>
+                "unterminated block comment".to_owned(),
+            DiagnosticKind::UnterminatedGroup =>
+                "group was never closed".to_owned(),
+            DiagnosticKind::MalformedNumber { text, reason } =>
+                format!("malformed number '{}': {}", text, reason),
+            DiagnosticKind::UnrecognizedCharacter { found } =>
+                format!("unrecognized character '{}'", found)
+        };
+        format!("{} at {}", message, self.span.debug_str())
+    }
+}
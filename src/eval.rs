@@ -0,0 +1,391 @@
+// Tree-walking evaluator. Runs after the lexer has produced a Group tree and
+// the expr layer has folded each group's children into precedence-correct
+// expressions; this module just computes the values.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::atom::expr::{Expr, ExprError};
+use crate::atom::{Group, GroupType, Identifier, IntegerLiteral, NumericLiteral, StringLiteral};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Num(f64),
+    Str(String),
+    List(Vec<Value>),
+    Unit
+}
+
+#[derive(Debug)]
+pub enum EvalError {
+    Expr(ExprError),
+    UnknownOperator(String),
+    TypeMismatch { op: String, lhs: String, rhs: String },
+    DivisionByZero,
+    NegativeExponent,
+    UnsupportedAtom(String),
+    UnknownIdentifier(String),
+    InvalidAssignmentTarget(String)
+}
+
+// One binding scope's worth of variables, declared by `=`. Mirrors
+// analysis::SymbolTable, but stores the runtime Value instead of a
+// statically-resolved Type.
+#[derive(Default, Debug)]
+struct Scope {
+    bindings: HashMap<String, Value>
+}
+
+impl Scope {
+    fn declare(&mut self, name: String, value: Value) {
+        self.bindings.insert(name, value);
+    }
+
+    fn get(&self, name: &str) -> Option<Value> {
+        self.bindings.get(name).cloned()
+    }
+}
+
+// Resolves a name against the innermost-first scope chain, same order as
+// analysis::resolve.
+fn resolve(scopes: &[Rc<RefCell<Scope>>], name: &str) -> Option<Value> {
+    scopes.iter().rev().find_map(|scope| scope.borrow().get(name))
+}
+
+pub struct Interpreter;
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        Interpreter
+    }
+
+    // Consumes a finished group: folds its children into expressions, then
+    // evaluates them according to what kind of group it is. Each group gets
+    // its own scope, pushed onto the chain for the duration of its body so
+    // nested groups can still see their enclosing scopes' bindings.
+    pub fn eval_group(&self, group: Group) -> Result<Value, EvalError> {
+        let mut scopes: Vec<Rc<RefCell<Scope>>> = Vec::new();
+        self.eval_group_scoped(group, &mut scopes)
+    }
+
+    fn eval_group_scoped(&self, group: Group, scopes: &mut Vec<Rc<RefCell<Scope>>>) -> Result<Value, EvalError> {
+        scopes.push(Rc::new(RefCell::new(Scope::default())));
+        let result = self.eval_group_body(group, scopes);
+        scopes.pop();
+        result
+    }
+
+    fn eval_group_body(&self, group: Group, scopes: &mut Vec<Rc<RefCell<Scope>>>) -> Result<Value, EvalError> {
+        let group_type = group.group_type();
+
+        // A list literal: comma/space-separated elements rather than
+        // `;`-terminated statements, folded into a Value::List.
+        if group_type == GroupType::Square {
+            let items = match group.into_list_items() {
+                Ok(items) => items,
+                Err(e) => return Err(EvalError::Expr(e))
+            };
+            let mut values = Vec::with_capacity(items.len());
+            for item in items {
+                values.push(self.eval_expr(item, scopes)?);
+            }
+            return Ok(Value::List(values));
+        }
+
+        let exprs = match group.into_expressions() {
+            Ok(exprs) => exprs,
+            Err(ExprError::EmptyGroup) => Vec::new(),
+            Err(e) => return Err(EvalError::Expr(e))
+        };
+
+        match group_type {
+            // A parenthesized subexpression: only the first statement is
+            // meaningful, an empty `()` evaluates to Unit.
+            GroupType::Curved => match exprs.into_iter().next() {
+                Some(expr) => self.eval_expr(expr, scopes),
+                None => Ok(Value::Unit)
+            },
+            // A statement block / the top-level program: its value is its
+            // last expression, or Unit if it has none.
+            GroupType::Curly | GroupType::Root => {
+                let mut last = Value::Unit;
+                for expr in exprs {
+                    last = self.eval_expr(expr, scopes)?;
+                }
+                Ok(last)
+            },
+            GroupType::Square => unreachable!("handled above")
+        }
+    }
+
+    fn eval_expr(&self, expr: Expr, scopes: &mut Vec<Rc<RefCell<Scope>>>) -> Result<Value, EvalError> {
+        match expr {
+            Expr::Leaf(atom) => self.eval_leaf(atom, scopes),
+            Expr::Unary { op, operand } => {
+                let value = self.eval_expr(*operand, scopes)?;
+                eval_unary(&op, value)
+            },
+            // "=" stores into the innermost scope rather than evaluating its
+            // lhs as a value: the lhs names the binding, it isn't read first.
+            Expr::Binary { op, lhs, rhs } if op == "=" => {
+                let name = assignment_target(*lhs)?;
+                let value = self.eval_expr(*rhs, scopes)?;
+                scopes.last().expect("eval_group_scoped always pushes a scope first")
+                    .borrow_mut().declare(name, value.clone());
+                Ok(value)
+            },
+            Expr::Binary { op, lhs, rhs } => {
+                let lhs = self.eval_expr(*lhs, scopes)?;
+                let rhs = self.eval_expr(*rhs, scopes)?;
+                eval_binary(&op, lhs, rhs)
+            }
+        }
+    }
+
+    fn eval_leaf(&self, atom: Box<dyn crate::atom::Atom>, scopes: &mut Vec<Rc<RefCell<Scope>>>) -> Result<Value, EvalError> {
+        if let Some(lit) = atom.as_any().downcast_ref::<IntegerLiteral>() {
+            return Ok(Value::Int(lit.value()));
+        }
+        if let Some(lit) = atom.as_any().downcast_ref::<NumericLiteral>() {
+            return Ok(Value::Num(lit.value()));
+        }
+        if let Some(lit) = atom.as_any().downcast_ref::<StringLiteral>() {
+            return Ok(Value::Str(lit.value().to_owned()));
+        }
+        if let Some(ident) = atom.as_any().downcast_ref::<Identifier>() {
+            return resolve(scopes, ident.value())
+                .ok_or_else(|| EvalError::UnknownIdentifier(ident.value().to_owned()));
+        }
+        if atom.as_any().downcast_ref::<Group>().is_some() {
+            let group = *atom.into_any().downcast::<Group>().unwrap();
+            return self.eval_group_scoped(group, scopes);
+        }
+        Err(EvalError::UnsupportedAtom(atom.debug_str(0)))
+    }
+}
+
+// Only a bare identifier is a valid assignment target (no destructuring or
+// indexing yet), so this unwraps the lhs Expr down to its name or rejects it.
+// Expr has no Debug/Display (it wraps Box<dyn Atom>, which doesn't require
+// either), so a rejected target is described by hand instead.
+fn assignment_target(lhs: Expr) -> Result<String, EvalError> {
+    match lhs {
+        Expr::Leaf(atom) => match atom.as_any().downcast_ref::<Identifier>() {
+            Some(ident) => Ok(ident.value().to_owned()),
+            None => Err(EvalError::InvalidAssignmentTarget(atom.debug_str(0)))
+        },
+        Expr::Unary { op, .. } => Err(EvalError::InvalidAssignmentTarget(format!("unary '{}' expression", op))),
+        Expr::Binary { op, .. } => Err(EvalError::InvalidAssignmentTarget(format!("binary '{}' expression", op)))
+    }
+}
+
+fn eval_unary(op: &str, value: Value) -> Result<Value, EvalError> {
+    match (op, value) {
+        ("-", Value::Int(i)) => Ok(Value::Int(-i)),
+        ("-", Value::Num(n)) => Ok(Value::Num(-n)),
+        ("!", Value::Int(i)) => Ok(Value::Int(if i == 0 { 1 } else { 0 })),
+        (op, value) => Err(EvalError::TypeMismatch { op: op.to_owned(), lhs: describe(&value), rhs: String::new() })
+    }
+}
+
+fn eval_binary(op: &str, lhs: Value, rhs: Value) -> Result<Value, EvalError> {
+    match op {
+        "+" | "-" | "*" | "/" | "%" | "^" => arithmetic(op, lhs, rhs),
+        "==" | "!=" | "<" | ">" | "<=" | ">=" => compare(op, lhs, rhs),
+        "&&" | "||" => logical(op, lhs, rhs),
+        _ => Err(EvalError::UnknownOperator(op.to_owned()))
+    }
+}
+
+// Arithmetic promotes Int to Num whenever either operand is already a Num.
+fn arithmetic(op: &str, lhs: Value, rhs: Value) -> Result<Value, EvalError> {
+    use Value::*;
+    match (lhs, rhs) {
+        (Int(a), Int(b)) => arithmetic_int(op, a, b),
+        (Num(a), Num(b)) => arithmetic_float(op, a, b).map(Num),
+        (Int(a), Num(b)) => arithmetic_float(op, a as f64, b).map(Num),
+        (Num(a), Int(b)) => arithmetic_float(op, a, b as f64).map(Num),
+        (Str(a), Str(b)) if op == "+" => Ok(Str(a + &b)),
+        (lhs, rhs) => Err(EvalError::TypeMismatch { op: op.to_owned(), lhs: describe(&lhs), rhs: describe(&rhs) })
+    }
+}
+
+fn arithmetic_int(op: &str, a: i64, b: i64) -> Result<Value, EvalError> {
+    let result = match op {
+        "+" => a.wrapping_add(b),
+        "-" => a.wrapping_sub(b),
+        "*" => a.wrapping_mul(b),
+        "/" => {
+            if b == 0 { return Err(EvalError::DivisionByZero); }
+            a / b
+        },
+        "%" => {
+            if b == 0 { return Err(EvalError::DivisionByZero); }
+            a % b
+        },
+        "^" => {
+            if b < 0 { return Err(EvalError::NegativeExponent); }
+            a.wrapping_pow(b as u32)
+        },
+        _ => return Err(EvalError::UnknownOperator(op.to_owned()))
+    };
+    Ok(Value::Int(result))
+}
+
+fn arithmetic_float(op: &str, a: f64, b: f64) -> Result<f64, EvalError> {
+    match op {
+        "+" => Ok(a + b),
+        "-" => Ok(a - b),
+        "*" => Ok(a * b),
+        "/" => Ok(a / b),
+        "%" => Ok(a % b),
+        "^" => Ok(a.powf(b)),
+        _ => Err(EvalError::UnknownOperator(op.to_owned()))
+    }
+}
+
+fn compare(op: &str, lhs: Value, rhs: Value) -> Result<Value, EvalError> {
+    use Value::*;
+    let ordering = match (&lhs, &rhs) {
+        (Int(a), Int(b)) => a.partial_cmp(b),
+        (Num(a), Num(b)) => a.partial_cmp(b),
+        (Int(a), Num(b)) => (*a as f64).partial_cmp(b),
+        (Num(a), Int(b)) => a.partial_cmp(&(*b as f64)),
+        (Str(a), Str(b)) => a.partial_cmp(b),
+        _ => None
+    }.ok_or_else(|| EvalError::TypeMismatch { op: op.to_owned(), lhs: describe(&lhs), rhs: describe(&rhs) })?;
+
+    let result = match op {
+        "==" => ordering.is_eq(),
+        "!=" => !ordering.is_eq(),
+        "<" => ordering.is_lt(),
+        ">" => ordering.is_gt(),
+        "<=" => ordering.is_le(),
+        ">=" => ordering.is_ge(),
+        _ => return Err(EvalError::UnknownOperator(op.to_owned()))
+    };
+    Ok(Value::Int(if result { 1 } else { 0 }))
+}
+
+fn logical(op: &str, lhs: Value, rhs: Value) -> Result<Value, EvalError> {
+    let lhs_bool = to_bool(&lhs).ok_or_else(|| EvalError::TypeMismatch { op: op.to_owned(), lhs: describe(&lhs), rhs: describe(&rhs) })?;
+    let rhs_bool = to_bool(&rhs).ok_or_else(|| EvalError::TypeMismatch { op: op.to_owned(), lhs: describe(&lhs), rhs: describe(&rhs) })?;
+    let result = match op {
+        "&&" => lhs_bool && rhs_bool,
+        "||" => lhs_bool || rhs_bool,
+        _ => return Err(EvalError::UnknownOperator(op.to_owned()))
+    };
+    Ok(Value::Int(if result { 1 } else { 0 }))
+}
+
+fn to_bool(value: &Value) -> Option<bool> {
+    match value {
+        Value::Int(i) => Some(*i != 0),
+        _ => None
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Int(_) => "Int".to_owned(),
+        Value::Num(_) => "Num".to_owned(),
+        Value::Str(_) => "Str".to_owned(),
+        Value::List(_) => "List".to_owned(),
+        Value::Unit => "Unit".to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::atom::lex;
+
+    fn eval(src: &str) -> Result<Value, EvalError> {
+        Interpreter::new().eval_group(lex(src))
+    }
+
+    #[test]
+    fn caret_raises_to_the_power() {
+        assert_eq!(eval("2 ^ 3;").unwrap(), Value::Int(8));
+    }
+
+    #[test]
+    fn caret_right_associates_when_evaluated() {
+        // 2 ^ (3 ^ 2) = 2 ^ 9 = 512, not (2 ^ 3) ^ 2 = 64.
+        assert_eq!(eval("2 ^ 3 ^ 2;").unwrap(), Value::Int(512));
+    }
+
+    #[test]
+    fn caret_on_floats_uses_powf() {
+        match eval("2.0 ^ 0.5;").unwrap() {
+            Value::Num(n) => assert!((n - std::f64::consts::SQRT_2).abs() < 1e-9),
+            other => panic!("expected a Num, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn negative_integer_exponent_is_an_error() {
+        match eval("2 ^ -1;") {
+            Err(EvalError::NegativeExponent) => {},
+            other => panic!("expected NegativeExponent, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn assignment_stores_and_yields_its_value() {
+        assert_eq!(eval("x = 1;").unwrap(), Value::Int(1));
+    }
+
+    #[test]
+    fn identifier_looks_up_its_bound_value() {
+        assert_eq!(eval("x = 1 + 2 * 3; y = x - 1;").unwrap(), Value::Int(6));
+    }
+
+    #[test]
+    fn reassignment_overwrites_the_previous_binding() {
+        assert_eq!(eval("x = 1; x = x + 1; x;").unwrap(), Value::Int(2));
+    }
+
+    #[test]
+    fn nested_block_sees_the_enclosing_scope() {
+        assert_eq!(eval("x = 5; { x + 1 };").unwrap(), Value::Int(6));
+    }
+
+    #[test]
+    fn unbound_identifier_is_an_error() {
+        match eval("x;") {
+            Err(EvalError::UnknownIdentifier(name)) => assert_eq!(name, "x"),
+            other => panic!("expected UnknownIdentifier, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn assigning_to_a_non_identifier_is_an_error() {
+        match eval("1 + 2 = 3;") {
+            Err(EvalError::InvalidAssignmentTarget(_)) => {},
+            other => panic!("expected InvalidAssignmentTarget, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn comma_separated_list_literal_holds_every_element() {
+        assert_eq!(eval("[1, 2, 3];").unwrap(), Value::List(vec![Value::Int(1), Value::Int(2), Value::Int(3)]));
+    }
+
+    #[test]
+    fn space_separated_list_literal_holds_every_element() {
+        assert_eq!(eval("[1 2 3];").unwrap(), Value::List(vec![Value::Int(1), Value::Int(2), Value::Int(3)]));
+    }
+
+    #[test]
+    fn empty_list_literal_is_an_empty_list() {
+        assert_eq!(eval("[];").unwrap(), Value::List(Vec::new()));
+    }
+
+    #[test]
+    fn list_elements_can_be_arbitrary_expressions() {
+        assert_eq!(eval("x = 1; [x, x + 1, x * 2];").unwrap(), Value::List(vec![Value::Int(1), Value::Int(2), Value::Int(2)]));
+    }
+}
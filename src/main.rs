@@ -4,7 +4,11 @@ use std::io::Read;
 use std::ops::DerefMut;
 use std::rc::Rc;
 
+mod analysis;
 mod atom;
+mod eval;
+
+use atom::Atom;
 
 
 
@@ -14,9 +18,33 @@ fn main() {
     let mut contents = String::new();
     file.read_to_string(&mut contents).unwrap();
 
-    let mut state: Box<dyn atom::Atom> =  Box::new(atom::Group::new(atom::GroupType::Root, None));
+    let mut pos = atom::span::Position::start();
+    let mut state: Box<dyn atom::Atom> = Box::new(atom::Group::new_root(pos));
     for c in contents.chars() {
-         state = state.read_char(c)
+         state = state.read_char(c, pos);
+         pos = pos.advance(c);
+    }
+    state = state.finish();
+
+    let root = *state.into_any().downcast::<atom::Group>()
+        .expect("finish() always bubbles back up to the Root group");
+
+    if std::env::args().any(|arg| arg == "--eval") {
+        let interpreter = eval::Interpreter::new();
+        match interpreter.eval_group(root) {
+            Ok(value) => println!("{:?}", value),
+            Err(err) => println!("eval error: {:?}", err)
+        }
+    } else if std::env::args().any(|arg| arg == "--analyze") {
+        let analysis = analysis::analyze(&root);
+        if analysis.errors.is_empty() {
+            println!("analysis: {} scope(s) resolved with no errors", analysis.tables.len());
+        } else {
+            for error in &analysis.errors {
+                println!("analysis error: {:?}", error);
+            }
+        }
+    } else {
+        println!("{}", root.debug_str(1));
     }
-    println!("{}",state.debug_str(1));
 }
\ No newline at end of file